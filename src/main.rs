@@ -3,69 +3,420 @@ use std::collections::HashMap;
 use std::env;
 use std::fs::File;
 use std::io;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
 use std::vec::Vec;
-use std::time::Instant;
 
 // Walkdir
 use walkdir::WalkDir;
 
+// Glob
+use glob::Pattern;
+
 // Blake3
-use blake3::Hasher;
+use blake3::Hasher as Blake3Hasher;
+
+// Xxhash
+use xxhash_rust::xxh3::Xxh3;
+
+// Crc32
+use crc32fast::Hasher as Crc32Hasher;
+
+// Rayon
+use rayon::prelude::*;
+
+// Serde
+use serde::{Deserialize, Serialize};
+
+// HashType selects which algorithm checksum and prefix_checksum use. BLAKE3
+// is the default: xxHash3 and CRC32 trade away collision resistance against
+// an adversary for raw speed, which is an acceptable trade on trusted local
+// dedup runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum HashType {
+    Blake3,
+    Xxh3,
+    Crc32,
+}
+
+impl HashType {
+    // parse turns a --hash value into a HashType, case-insensitively.
+    fn parse(value: &str) -> Option<HashType> {
+        match value.to_lowercase().as_str() {
+            "blake3" => Some(HashType::Blake3),
+            "xxh3" => Some(HashType::Xxh3),
+            "crc32" => Some(HashType::Crc32),
+            _ => None,
+        }
+    }
+}
+
+// FileHasher is implemented by every hash algorithm checksum can dispatch
+// to, so the read loop in checksum/prefix_checksum stays the same
+// regardless of which one is chosen.
+trait FileHasher {
+    fn update(&mut self, data: &[u8]);
+    fn finish_hex(self: Box<Self>) -> String;
+}
+
+impl FileHasher for Blake3Hasher {
+    fn update(&mut self, data: &[u8]) {
+        Blake3Hasher::update(self, data);
+    }
+
+    fn finish_hex(self: Box<Self>) -> String {
+        self.finalize().to_string()
+    }
+}
+
+impl FileHasher for Xxh3 {
+    fn update(&mut self, data: &[u8]) {
+        Xxh3::update(self, data);
+    }
+
+    fn finish_hex(self: Box<Self>) -> String {
+        format!("{:016x}", self.digest())
+    }
+}
+
+impl FileHasher for Crc32Hasher {
+    fn update(&mut self, data: &[u8]) {
+        Crc32Hasher::update(self, data);
+    }
+
+    fn finish_hex(self: Box<Self>) -> String {
+        format!("{:08x}", self.finalize())
+    }
+}
+
+fn new_hasher(hash_type: HashType) -> Box<dyn FileHasher> {
+    match hash_type {
+        HashType::Blake3 => Box::new(Blake3Hasher::new()),
+        HashType::Xxh3 => Box::new(Xxh3::new()),
+        HashType::Crc32 => Box::new(Crc32Hasher::new()),
+    }
+}
+
+// OutputFormat selects how print_duplicates renders duplicate groups.
+// fdupes mirrors the classic fdupes CLI; json is for scripts that want
+// structure; machine null-separates paths so it pipes safely into
+// `xargs -0` even when paths contain spaces or newlines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Fdupes,
+    Json,
+    Machine,
+}
+
+impl OutputFormat {
+    // parse turns a --format value into an OutputFormat, case-insensitively.
+    fn parse(value: &str) -> Option<OutputFormat> {
+        match value.to_lowercase().as_str() {
+            "fdupes" => Some(OutputFormat::Fdupes),
+            "json" => Some(OutputFormat::Json),
+            "machine" => Some(OutputFormat::Machine),
+            _ => None,
+        }
+    }
+}
+
+// CacheEntry is one record of the on-disk hash cache: the size and
+// modification time a file had when it was last hashed, the hash algorithm
+// used, and the hash that came out of it. A file only reuses a cached hash
+// while size, modification time, and hash algorithm all still match.
+#[derive(Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    modified: u64,
+    hash_type: HashType,
+    hash: String,
+}
+
+// load_cache reads a previously persisted hash cache from disk. A missing
+// file is treated as an empty cache, since the cache is opt-in and the
+// first run over a directory will not have one yet.
+fn load_cache(path: &str) -> Result<HashMap<PathBuf, CacheEntry>, io::Error> {
+    match File::open(path) {
+        Ok(f) => serde_json::from_reader(f).map_err(io::Error::from),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e),
+    }
+}
+
+// save_cache persists the hash cache to disk as JSON, dropping entries for
+// paths that no longer exist so the cache file doesn't grow without bound
+// across repeated runs over a tree whose files come and go.
+fn save_cache(path: &str, cache: &HashMap<PathBuf, CacheEntry>) -> Result<(), io::Error> {
+    let cache: HashMap<&PathBuf, &CacheEntry> =
+        cache.iter().filter(|(path, _)| path.exists()).collect();
+    let f = File::create(path)?;
+    serde_json::to_writer(f, &cache).map_err(io::Error::from)
+}
+
+// to_epoch_secs converts a SystemTime to whole seconds since the Unix
+// epoch, so modification times can round-trip through the JSON cache.
+fn to_epoch_secs(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+// USAGE is printed whenever argument parsing can't proceed, either because
+// the positional directory is missing or an unrecognized flag was given.
+const USAGE: &str =
+    "Usage rdups [--hash blake3|xxh3|crc32] [--cache FILE] [--format fdupes|json|machine] \
+     [--exclude GLOB]... [--include-ext EXT]... [--exclude-ext EXT]... \
+     [--action report|hardlink|delete] [--dry-run] DIRECTORY";
 
 fn main() -> Result<(), io::Error> {
     // Parse arguments.
     let args: Vec<String> = env::args().collect();
-    let path = match args.get(1) {
+    let parsed = parse_args(&args);
+    if !parsed.unknown.is_empty() {
+        for msg in &parsed.unknown {
+            println!("{}", msg);
+        }
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    let path = match parsed.path {
         Some(path) => path,
         None => {
-            println!("Usage rdups DIRECTORY");
+            println!("{}", USAGE);
             return Ok(());
         }
     };
 
+    // Load the hash cache, if one was requested.
+    let mut cache = match parsed.cache_path {
+        Some(cache_path) => load_cache(cache_path)?,
+        None => HashMap::new(),
+    };
+
     // Walk all files.
     let start = Instant::now();
-    let files = walk_files(path)?;
-    println!("walk files: {:?}", start.elapsed());
+    let files = walk_files(path, &parsed.filters)?;
+    eprintln!("walk files: {:?}", start.elapsed());
 
     // Group all files by size.
     let start = Instant::now();
     let group_by_size = group_files_by_size(files);
-    println!("group by size: {:?}", start.elapsed());
+    eprintln!("group by size: {:?}", start.elapsed());
 
     // Group all files by checksum.
     let start = Instant::now();
-    let group_by_checksum = group_files_by_checksum(group_by_size)?;
-    println!("group by checksum: {:?}", start.elapsed());
+    let group_by_checksum = group_files_by_checksum(group_by_size, parsed.hash_type, &mut cache)?;
+    eprintln!("group by checksum: {:?}", start.elapsed());
 
     // Get all duplicated files, grouped by checksum.
     let dups = duplicated_files(group_by_checksum);
 
-    // Print all duplicated files to terminal.
-    for (_, files) in dups {
-        for path in files {
-            println!("{:?}", path);
-        }
-        println!("");
+    // Print all duplicated files in the requested format.
+    print_duplicates(&dups, parsed.format)?;
+
+    // Apply the requested action to the confirmed duplicates.
+    apply_action(&dups, parsed.action, parsed.dry_run)?;
+
+    // Persist the updated hash cache, if one was requested.
+    if let Some(cache_path) = parsed.cache_path {
+        save_cache(cache_path, &cache)?;
     }
 
     Ok(())
 }
 
+// PathFilters decides, ahead of opening or stat-ing a file, whether
+// walk_files should skip it entirely: entries matching an --exclude glob
+// are always dropped, entries whose extension is in --exclude-ext are
+// dropped, and, if --include-ext was given at least once, only entries
+// whose extension is in that list are kept.
+struct PathFilters {
+    excludes: Vec<Pattern>,
+    include_ext: Vec<String>,
+    exclude_ext: Vec<String>,
+}
+
+impl PathFilters {
+    fn accepts(&self, path: &Path) -> bool {
+        if self
+            .excludes
+            .iter()
+            .any(|pattern| pattern.matches_path(path))
+        {
+            return false;
+        }
+
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+
+        match &ext {
+            Some(ext) if self.exclude_ext.contains(ext) => return false,
+            _ => {}
+        }
+
+        if self.include_ext.is_empty() {
+            return true;
+        }
+        matches!(&ext, Some(ext) if self.include_ext.contains(ext))
+    }
+}
+
+// Action selects what main does with the confirmed duplicate groups,
+// beyond reporting them. Hardlink and Delete are destructive and respect
+// --dry-run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Action {
+    Report,
+    Hardlink,
+    Delete,
+}
+
+impl Action {
+    // parse turns a --action value into an Action, case-insensitively.
+    fn parse(value: &str) -> Option<Action> {
+        match value.to_lowercase().as_str() {
+            "report" => Some(Action::Report),
+            "hardlink" => Some(Action::Hardlink),
+            "delete" => Some(Action::Delete),
+            _ => None,
+        }
+    }
+}
+
+// Args holds everything parse_args pulls out of argv.
+struct Args<'a> {
+    path: Option<&'a String>,
+    hash_type: HashType,
+    cache_path: Option<&'a String>,
+    format: OutputFormat,
+    filters: PathFilters,
+    action: Action,
+    dry_run: bool,
+    unknown: Vec<String>,
+}
+
+// parse_args pulls the optional --hash, --cache, --format, --exclude,
+// --include-ext, --exclude-ext, --action, and --dry-run flags out of the
+// argument list and returns them alongside the remaining positional
+// directory argument. --exclude, --include-ext, and --exclude-ext are
+// repeatable; unparsable --exclude globs are ignored. Any other argument
+// starting with "--", and any unrecognized value given to --hash,
+// --format, or --action, is reported in unknown instead of silently
+// falling back to a default or being mistaken for the positional
+// directory argument.
+fn parse_args(args: &[String]) -> Args<'_> {
+    let mut path = None;
+    let mut hash_type = HashType::Blake3;
+    let mut cache_path = None;
+    let mut format = OutputFormat::Fdupes;
+    let mut excludes = Vec::new();
+    let mut include_ext = Vec::new();
+    let mut exclude_ext = Vec::new();
+    let mut action = Action::Report;
+    let mut dry_run = false;
+    let mut unknown = Vec::new();
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == "--hash" {
+            if let Some(value) = iter.next() {
+                match HashType::parse(value) {
+                    Some(parsed) => hash_type = parsed,
+                    None => unknown.push(format!("unknown --hash value: {}", value)),
+                }
+            }
+        } else if arg == "--cache" {
+            cache_path = iter.next();
+        } else if arg == "--format" {
+            if let Some(value) = iter.next() {
+                match OutputFormat::parse(value) {
+                    Some(parsed) => format = parsed,
+                    None => unknown.push(format!("unknown --format value: {}", value)),
+                }
+            }
+        } else if arg == "--exclude" {
+            if let Some(value) = iter.next() {
+                if let Ok(pattern) = Pattern::new(value) {
+                    excludes.push(pattern);
+                }
+            }
+        } else if arg == "--include-ext" {
+            if let Some(value) = iter.next() {
+                include_ext.push(value.to_lowercase());
+            }
+        } else if arg == "--exclude-ext" {
+            if let Some(value) = iter.next() {
+                exclude_ext.push(value.to_lowercase());
+            }
+        } else if arg == "--action" {
+            if let Some(value) = iter.next() {
+                match Action::parse(value) {
+                    Some(parsed) => action = parsed,
+                    None => unknown.push(format!("unknown --action value: {}", value)),
+                }
+            }
+        } else if arg == "--dry-run" {
+            dry_run = true;
+        } else if arg.starts_with("--") {
+            unknown.push(format!("unknown argument: {}", arg));
+        } else if path.is_none() {
+            path = Some(arg);
+        }
+    }
+
+    Args {
+        path,
+        hash_type,
+        cache_path,
+        format,
+        filters: PathFilters {
+            excludes,
+            include_ext,
+            exclude_ext,
+        },
+        action,
+        dry_run,
+        unknown,
+    }
+}
+
+// FileEntry is a walked file's size, path, and last modification time.
+// The modification time is only needed to key the on-disk hash cache.
+struct FileEntry {
+    size: u64,
+    modified: SystemTime,
+    path: PathBuf,
+}
+
 // walk_files, walk all files in all subdirectories.
-// Return a vector with size and file path.
-fn walk_files(path: &str) -> Result<Vec<(u64, PathBuf)>, io::Error> {
-    let mut files: Vec<(u64, PathBuf)> = Vec::new();
+// Return a vector with size, modification time, and file path.
+//
+// Entries rejected by filters are skipped before they are ever opened or
+// stat-ed, so excluded caches, .git directories, or non-matching
+// extensions cost nothing beyond the directory listing itself.
+fn walk_files(path: &str, filters: &PathFilters) -> Result<Vec<FileEntry>, io::Error> {
+    let mut files: Vec<FileEntry> = Vec::new();
 
     for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
-        if entry.file_type().is_file() {
-            let file = File::open(entry.path())?;
-            let file_metadata = file.metadata()?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if !filters.accepts(entry.path()) {
+            continue;
+        }
 
-            if file_metadata.len() != 0 {
-                files.push((file_metadata.len(), entry.path().to_path_buf()));
-            }
+        let file = File::open(entry.path())?;
+        let file_metadata = file.metadata()?;
+
+        if file_metadata.len() != 0 {
+            files.push(FileEntry {
+                size: file_metadata.len(),
+                modified: file_metadata.modified()?,
+                path: entry.path().to_path_buf(),
+            });
         }
     }
     Ok(files)
@@ -73,56 +424,369 @@ fn walk_files(path: &str) -> Result<Vec<(u64, PathBuf)>, io::Error> {
 
 // group_files_by_size group all files by file size. Using a
 // vector with size and path.
-fn group_files_by_size(files: Vec<(u64, PathBuf)>) -> HashMap<u64, Vec<PathBuf>> {
-    let mut groups: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+fn group_files_by_size(files: Vec<FileEntry>) -> HashMap<u64, Vec<FileEntry>> {
+    let mut groups: HashMap<u64, Vec<FileEntry>> = HashMap::new();
 
-    for (size, path) in files {
-        groups.entry(size).or_default().push(path);
+    for file in files {
+        groups.entry(file.size).or_default().push(file);
     }
     groups
 }
 
+// PREFIX_HASH_SIZE is the number of leading bytes hashed during the prefix
+// pre-filtering stage in group_files_by_checksum.
+const PREFIX_HASH_SIZE: u64 = 1024 * 1024;
+
+// ChecksumKey identifies a group of identical files: their shared size
+// plus the hash that confirmed it. Keying on size as well as hash means a
+// lone large file confirmed only by its prefix hash can never collide with
+// an unrelated file of a different size that happens to share the same
+// leading bytes.
+type ChecksumKey = (u64, String);
+
 // group_files_by_checksum group all files by checksum. Using blake3 to calculate a
 // checksum for the files.
+//
+// Before hashing whole files, each size group is first split into
+// sub-groups keyed by a cheap "prefix hash" of only the leading
+// PREFIX_HASH_SIZE bytes. Large files that already differ within that
+// prefix are ruled out without reading the rest of them. Only sub-groups
+// that still have more than one candidate proceed to a full-file hash;
+// a sub-group left with a single large file is confirmed by its prefix
+// hash alone, since the final grouping key is (size, hash) and a prefix
+// hash can only collide with another prefix hash of the same size, never
+// with a full hash or a prefix hash from a different size group. Such a
+// prefix-only hash is never written to the cache, and a single large file
+// whose size group had a cache hit is fully hashed instead, so it can still
+// match the cached file's full hash. Files no
+// bigger than the prefix are already fully hashed, so the prefix hash is
+// their final checksum too.
+//
+// Both the prefix pass and the full-hash pass are run through rayon, since
+// hashing is I/O- and CPU-bound work that parallelizes well across files.
+//
+// Before either pass, every candidate is checked against the hash cache:
+// if a file's size, modification time, and hash algorithm still match a
+// cached entry, its stored full-file hash is reused directly and neither
+// pass touches it. A cache entry recorded under a different --hash
+// algorithm is treated as a miss, since hashes from different algorithms
+// are never comparable.
 fn group_files_by_checksum(
-    files: HashMap<u64, Vec<PathBuf>>,
-) -> Result<HashMap<String, Vec<PathBuf>>, io::Error> {
-    let mut groups: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    files: HashMap<u64, Vec<FileEntry>>,
+    hash_type: HashType,
+    cache: &mut HashMap<PathBuf, CacheEntry>,
+) -> Result<HashMap<ChecksumKey, Vec<PathBuf>>, io::Error> {
+    let mut groups: HashMap<ChecksumKey, Vec<PathBuf>> = HashMap::new();
 
-    for (_, files) in files {
-        if files.len() > 1 {
-            for path in files {
-                let sum = blake3_checksum(&path)?;
-                groups.entry(sum).or_default().push(path);
+    // Flatten every size group with more than one file into candidates that
+    // still need hashing, splitting off cache hits straight into groups.
+    let mut candidates: Vec<(u64, PathBuf)> = Vec::new();
+    let mut meta_by_path: HashMap<PathBuf, (u64, u64)> = HashMap::new();
+    let mut cached_sizes: std::collections::HashSet<u64> = std::collections::HashSet::new();
+    for (size, entries) in files {
+        if entries.len() <= 1 {
+            continue;
+        }
+        for entry in entries {
+            let modified = to_epoch_secs(entry.modified);
+            match cache.get(&entry.path) {
+                Some(cached)
+                    if cached.size == entry.size
+                        && cached.modified == modified
+                        && cached.hash_type == hash_type =>
+                {
+                    cached_sizes.insert(entry.size);
+                    groups
+                        .entry((entry.size, cached.hash.clone()))
+                        .or_default()
+                        .push(entry.path);
+                }
+                _ => {
+                    meta_by_path.insert(entry.path.clone(), (entry.size, modified));
+                    candidates.push((size, entry.path));
+                }
             }
         }
     }
+
+    let prefix_sums: Vec<(ChecksumKey, PathBuf)> = candidates
+        .into_par_iter()
+        .map(|(size, path)| prefix_checksum(&path, hash_type).map(|sum| ((size, sum), path)))
+        .collect::<Result<Vec<_>, io::Error>>()?;
+
+    let mut prefix_groups: HashMap<ChecksumKey, Vec<PathBuf>> = HashMap::new();
+    for (key, path) in prefix_sums {
+        prefix_groups.entry(key).or_default().push(path);
+    }
+
+    let mut to_hash: Vec<(u64, PathBuf)> = Vec::new();
+    let mut finalized: Vec<(ChecksumKey, PathBuf)> = Vec::new();
+    for ((size, prefix_sum), paths) in prefix_groups {
+        if size <= PREFIX_HASH_SIZE {
+            for path in paths {
+                finalized.push(((size, prefix_sum.clone()), path));
+            }
+        } else if paths.len() <= 1 && !cached_sizes.contains(&size) {
+            // Confirmed by prefix hash only: grouped, but not cached, since
+            // the cache holds full-file hashes.
+            for path in paths {
+                groups.entry((size, prefix_sum.clone())).or_default().push(path);
+            }
+        } else {
+            to_hash.extend(paths.into_iter().map(|path| (size, path)));
+        }
+    }
+
+    let sums: Vec<(ChecksumKey, PathBuf)> = to_hash
+        .into_par_iter()
+        .map(|(size, path)| checksum(&path, hash_type).map(|sum| ((size, sum), path)))
+        .collect::<Result<Vec<_>, io::Error>>()?;
+
+    finalized.extend(sums);
+
+    for ((size, sum), path) in finalized {
+        if let Some((_, modified)) = meta_by_path.get(&path) {
+            cache.insert(
+                path.clone(),
+                CacheEntry {
+                    size,
+                    modified: *modified,
+                    hash_type,
+                    hash: sum.clone(),
+                },
+            );
+        }
+        groups.entry((size, sum)).or_default().push(path);
+    }
+
     Ok(groups)
 }
 
 // duplicated_files check if the HashMap with checksum and files,
 // has more then one file in vector. If more then one, its a duplicated file.
-fn duplicated_files(files: HashMap<String, Vec<PathBuf>>) -> HashMap<String, Vec<PathBuf>> {
-    let mut dups: HashMap<String, Vec<PathBuf>> = HashMap::new();
+fn duplicated_files(
+    files: HashMap<ChecksumKey, Vec<PathBuf>>,
+) -> HashMap<ChecksumKey, Vec<PathBuf>> {
+    let mut dups: HashMap<ChecksumKey, Vec<PathBuf>> = HashMap::new();
 
-    for (sum, files) in files {
+    for (key, files) in files {
         if files.len() > 1 {
             for path in files {
-                dups.entry(sum.clone()).or_default().push(path);
+                dups.entry(key.clone()).or_default().push(path);
             }
         }
     }
     dups
 }
 
-// blake3_checksum read file, get BLAKE3 checksum.
-fn blake3_checksum(path: &PathBuf) -> Result<String, io::Error> {
+// DuplicateGroup is the JSON shape of one duplicate group: the hash the
+// group shares and the paths that hash to it.
+#[derive(Serialize)]
+struct DuplicateGroup {
+    hash: String,
+    paths: Vec<String>,
+}
+
+// print_duplicates prints duplicate groups to stdout in the requested
+// OutputFormat.
+fn print_duplicates(
+    dups: &HashMap<ChecksumKey, Vec<PathBuf>>,
+    format: OutputFormat,
+) -> Result<(), io::Error> {
+    match format {
+        OutputFormat::Fdupes => {
+            for paths in dups.values() {
+                for path in paths {
+                    println!("{}", path.display());
+                }
+                println!();
+            }
+        }
+        OutputFormat::Machine => {
+            for paths in dups.values() {
+                for path in paths {
+                    print!("{}\0", path.display());
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let groups: Vec<DuplicateGroup> = dups
+                .iter()
+                .map(|((_, hash), paths)| DuplicateGroup {
+                    hash: hash.clone(),
+                    paths: paths.iter().map(|p| p.display().to_string()).collect(),
+                })
+                .collect();
+            let json = serde_json::to_string_pretty(&groups).map_err(io::Error::from)?;
+            println!("{}", json);
+        }
+    }
+    Ok(())
+}
+
+// apply_action performs the requested Action on every duplicate group,
+// keeping each group's lexicographically-first path as the canonical file
+// and acting on the rest. Report does nothing further; Hardlink and Delete
+// respect dry_run, which prints the exact operations without performing
+// them. A pair that fails (for example because a file changed on disk
+// since it was hashed) is logged and skipped; it does not abort the
+// groups still left to process.
+fn apply_action(
+    dups: &HashMap<ChecksumKey, Vec<PathBuf>>,
+    action: Action,
+    dry_run: bool,
+) -> Result<(), io::Error> {
+    if action == Action::Report {
+        return Ok(());
+    }
+
+    for paths in dups.values() {
+        let mut sorted = paths.clone();
+        sorted.sort();
+        let canonical = match sorted.first() {
+            Some(canonical) => canonical,
+            None => continue,
+        };
+
+        for duplicate in &sorted[1..] {
+            let result = match action {
+                Action::Report => Ok(()),
+                Action::Hardlink => replace_with_hardlink(canonical, duplicate, dry_run),
+                Action::Delete => delete_duplicate(duplicate, canonical, dry_run),
+            };
+            if let Err(e) = result {
+                eprintln!("warning: {}, skipping {:?}", e, duplicate);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// verify_still_duplicate re-checks that two files still share size and
+// hash right before an action acts on them, since a run over a large tree
+// can take a while and the files on disk may have changed in the meantime.
+// This always hashes with BLAKE3 regardless of the --hash algorithm the
+// run was grouped with: xxHash3 and CRC32 trade away collision resistance
+// for speed, which is fine for grouping candidates but not an acceptable
+// risk for the last check before an irreversible hardlink or delete.
+fn verify_still_duplicate(a: &PathBuf, b: &PathBuf) -> Result<(), io::Error> {
+    let size_a = std::fs::metadata(a)?.len();
+    let size_b = std::fs::metadata(b)?.len();
+    if size_a != size_b {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{:?} and {:?} no longer share the same size", a, b),
+        ));
+    }
+
+    if checksum(a, HashType::Blake3)? != checksum(b, HashType::Blake3)? {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{:?} and {:?} no longer share the same hash", a, b),
+        ));
+    }
+
+    Ok(())
+}
+
+// replace_with_hardlink replaces duplicate with a hardlink to canonical.
+// The link is first created under a temporary name in duplicate's own
+// directory, then renamed over duplicate, so an interrupted run never
+// leaves duplicate missing without a replacement.
+fn replace_with_hardlink(
+    canonical: &PathBuf,
+    duplicate: &PathBuf,
+    dry_run: bool,
+) -> Result<(), io::Error> {
+    verify_still_duplicate(canonical, duplicate)?;
+
+    if dry_run {
+        println!(
+            "hardlink {} -> {}",
+            duplicate.display(),
+            canonical.display()
+        );
+        return Ok(());
+    }
+
+    let parent = duplicate.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = duplicate
+        .file_name()
+        .unwrap_or_else(|| std::ffi::OsStr::new("rdups"));
+    let tmp = parent.join(format!(".{}.rdups-tmp", file_name.to_string_lossy()));
+
+    std::fs::hard_link(canonical, &tmp)?;
+    std::fs::rename(&tmp, duplicate)?;
+    println!(
+        "hardlinked {} -> {}",
+        duplicate.display(),
+        canonical.display()
+    );
+
+    Ok(())
+}
+
+// delete_duplicate removes duplicate after verifying it still matches
+// canonical.
+fn delete_duplicate(
+    duplicate: &PathBuf,
+    canonical: &PathBuf,
+    dry_run: bool,
+) -> Result<(), io::Error> {
+    verify_still_duplicate(canonical, duplicate)?;
+
+    if dry_run {
+        println!("delete {}", duplicate.display());
+        return Ok(());
+    }
+
+    std::fs::remove_file(duplicate)?;
+    println!("deleted {}", duplicate.display());
+
+    Ok(())
+}
+
+// checksum read a whole file and get its checksum, using whichever
+// FileHasher hash_type selects.
+fn checksum(path: &PathBuf, hash_type: HashType) -> Result<String, io::Error> {
     // Open file.
     let mut f = File::open(path)?;
 
-    // Create a new BLAKE3, copy, then read checksum.
-    let mut hasher = Hasher::new();
-    let _ = io::copy(&mut f, &mut hasher);
+    // Create the selected hasher, read the whole file through it, then
+    // read the checksum.
+    let mut hasher = new_hasher(hash_type);
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = f.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+
+    Ok(hasher.finish_hex())
+}
+
+// prefix_checksum read only the first PREFIX_HASH_SIZE bytes of a file and
+// get their checksum, using whichever FileHasher hash_type selects. Used
+// to cheaply pre-filter size groups before committing to a full checksum.
+fn prefix_checksum(path: &PathBuf, hash_type: HashType) -> Result<String, io::Error> {
+    // Open file.
+    let f = File::open(path)?;
+
+    // Create the selected hasher, read at most the prefix through it, then
+    // read the checksum.
+    let mut hasher = new_hasher(hash_type);
+    let mut prefix = f.take(PREFIX_HASH_SIZE);
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = prefix.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
 
-    Ok(format!("{}", hasher.finalize().to_string()))
+    Ok(hasher.finish_hex())
 }